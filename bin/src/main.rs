@@ -1,7 +1,16 @@
+use std::io::BufRead;
 use std::str::FromStr;
 
 use clap::{AppSettings, Clap};
 use indoc::indoc;
+use structured_packer_logs::{
+    event::Event,
+    log::{Decodeable, EventLog},
+};
+
+mod output;
+
+use output::Aggregate;
 
 #[derive(Debug)]
 enum Filter {
@@ -18,7 +27,7 @@ impl FromStr for Filter {
             "builds" => Self::Builds,
             "messages" => Self::Messages,
             "artifacts" => Self::Artifacts,
-            _ => Err(format!("{} does not match any filtereable event", s))?
+            _ => Err(format!("{} does not match any filtereable event", s))?,
         })
     }
 }
@@ -29,34 +38,109 @@ impl FromStr for Filter {
 #[clap(version = "0.1.0", author = "Mathias Pius <contact@pius.io>")]
 #[clap(setting = AppSettings::ColoredHelp)]
 struct Opts {
+    #[clap(about = indoc! {"
+        Path to a packer machine-readable log file. If omitted, lines are
+        read from stdin instead, so `packer build -machine-readable | sp-logs`
+        works directly.
+    "})]
     input: Option<String>,
     #[clap(short, long, about = indoc! {"
         If set, aggregates the output into a single document containing
         all the information from the log, instead of outputting individual
-        events as they happen.        
+        events as they happen.
     "})]
     aggregate: bool,
     #[clap(short, long, about = indoc! {"
-        Hello world
+        Restricts output to events of the given kind(s): builds, artifacts
+        or messages. May be specified multiple times, in which case any
+        matching event is emitted.
     "})]
     filter: Vec<Filter>,
+    #[clap(short = 'w', long, about = indoc! {"
+        Follow the input file as it grows, similar to `tail -f`, emitting
+        events as they are appended to the log instead of exiting once the
+        current contents have been read. Requires an input file, since
+        stdin can't be rewound or re-watched.
+    "})]
+    follow: bool,
 }
 
 fn main() {
     let opts = Opts::parse();
 
+    if opts.aggregate && opts.follow {
+        // `follow()` only returns once the watcher is dropped, so the
+        // aggregate would never actually be printed; these two options
+        // are mutually exclusive.
+        eprintln!(
+            "--aggregate cannot be combined with --follow, since follow never reaches end of input"
+        );
+        std::process::exit(1);
+    }
+
+    let mut aggregate = Aggregate::default();
+
+    let mut on_event = |event: Event| {
+        if opts.aggregate {
+            aggregate.record(&event);
+        } else {
+            output::emit(&event, &opts.filter);
+        }
+    };
+
+    if opts.follow {
+        let input = match opts.input.as_ref() {
+            Some(input) => input,
+            None => {
+                eprintln!(
+                    "--follow requires an input file to watch, since stdin can't be re-watched"
+                );
+                std::process::exit(1);
+            }
+        };
+
+        structured_packer_logs::follow::follow(input, on_event)
+            .expect("failed to follow input file");
+    } else if let Some(input) = &opts.input {
+        let contents = std::fs::read_to_string(input).expect("failed to read input file");
+        let mut log = EventLog::default();
+        for line in contents.lines() {
+            if let Err(err) = log.try_decode(line.split(","), &mut on_event) {
+                eprintln!("skipping unparseable line: {}", err);
+            }
+        }
+    } else {
+        // No input file given: follow the `tool < file` / `other-tool |
+        // tool` convention and decode lines from stdin as they arrive.
+        let stdin = std::io::stdin();
+        let mut log = EventLog::default();
+        for line in stdin.lock().lines() {
+            let line = line.expect("failed to read line from stdin");
+            if let Err(err) = log.try_decode(line.split(","), &mut on_event) {
+                eprintln!("skipping unparseable line: {}", err);
+            }
+        }
+    }
+
+    if opts.aggregate {
+        println!(
+            "{}",
+            serde_json::to_string(&aggregate).expect("aggregate is always representable as JSON")
+        );
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::error::Error;
-
-    use clap::Clap;
     use crate::Opts;
+    use clap::Clap;
 
     #[test]
     fn test_parsing() {
-        let err = Opts::try_parse_from(&["bin-name", "-f", "unknown_event"]).expect_err("filtering by unknown_event should error");
-        assert_eq!(err.source().unwrap().to_string(), "unknown_event does not match any filtereable event");
+        let err = Opts::try_parse_from(["bin-name", "-f", "unknown_event"])
+            .expect_err("filtering by unknown_event should error");
+        assert!(err
+            .to_string()
+            .contains("unknown_event does not match any filtereable event"));
     }
 }
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+use structured_packer_logs::event::{Artifact, Event, EventKind, UI};
+
+use crate::Filter;
+
+/// Accumulates builds, artifacts and messages across an entire run so they
+/// can be emitted as a single document at EOF, instead of streaming
+/// individual events as they're decoded.
+#[derive(Debug, Default, Serialize)]
+pub struct Aggregate {
+    builds: HashMap<String, Vec<Artifact>>,
+    messages: Vec<UI>,
+}
+
+impl Aggregate {
+    pub fn record(&mut self, event: &Event) {
+        match event.kind() {
+            EventKind::Artifact {
+                build_name,
+                artifact,
+            } => self
+                .builds
+                .entry(build_name.clone())
+                .or_default()
+                .push(artifact.clone()),
+            EventKind::UI(ui) => self.messages.push(ui.clone()),
+            EventKind::BuildMessage { message, .. } => self.messages.push(message.clone()),
+            EventKind::Build { .. }
+            | EventKind::Version { .. }
+            | EventKind::VersionCommit { .. }
+            | EventKind::VersionPrerelease { .. }
+            | EventKind::Unknown { .. } => {}
+        }
+    }
+}
+
+/// Returns whether `kind` passes any of the given filters. An empty filter
+/// list matches everything, and multiple filters are additive.
+pub fn matches(filters: &[Filter], kind: &EventKind) -> bool {
+    filters.is_empty()
+        || filters.iter().any(|filter| {
+            matches!(
+                (filter, kind),
+                (Filter::Builds, EventKind::Build { .. })
+                    | (Filter::Artifacts, EventKind::Artifact { .. })
+                    | (Filter::Messages, EventKind::UI(_))
+                    | (Filter::Messages, EventKind::BuildMessage { .. })
+            )
+        })
+}
+
+/// Emits `event` as a line of newline-delimited JSON, honoring `--filter`.
+pub fn emit(event: &Event, filters: &[Filter]) {
+    if matches(filters, event.kind()) {
+        println!(
+            "{}",
+            serde_json::to_string(event).expect("event is always representable as JSON")
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use structured_packer_logs::log::{Decodeable, EventLog};
+
+    const RAW_LOG: &str = concat!(
+        "1643024800,,ui,say,Packer v1.7.0\n",
+        "1643024801,first-build,ui,say,starting\n",
+        "1643024810,first-build,artifact-count,1\n",
+        "1643024811,first-build,artifact,0,builder-id,mitchellh.virtualbox\n",
+        "1643024811,first-build,artifact,0,id,\n",
+        "1643024811,first-build,artifact,0,string,VM files\n",
+        "1643024811,first-build,artifact,0,files-count,0\n",
+        "1643024811,first-build,artifact,0,end\n",
+        "1643024820,second-build,artifact-count,1\n",
+        "1643024821,second-build,artifact,0,builder-id,mitchellh.virtualbox\n",
+        "1643024821,second-build,artifact,0,id,\n",
+        "1643024821,second-build,artifact,0,string,VM files\n",
+        "1643024821,second-build,artifact,0,files-count,0\n",
+        "1643024821,second-build,artifact,0,end\n",
+    );
+
+    fn decode_all(raw: &str) -> Vec<Event> {
+        let mut log = EventLog::default();
+        let mut events = Vec::new();
+        for line in raw.lines() {
+            log.try_decode(line.split(","), |event| events.push(event))
+                .unwrap();
+        }
+        events
+    }
+
+    #[test]
+    fn aggregate_groups_artifacts_by_build_name() {
+        let mut aggregate = Aggregate::default();
+        for event in &decode_all(RAW_LOG) {
+            aggregate.record(event);
+        }
+
+        assert_eq!(aggregate.builds.len(), 2);
+        assert_eq!(aggregate.builds["first-build"].len(), 1);
+        assert_eq!(aggregate.builds["second-build"].len(), 1);
+        // The global `ui` message and the per-build `ui` message both land
+        // in `messages`, regardless of which build they're tied to.
+        assert_eq!(aggregate.messages.len(), 2);
+    }
+
+    #[test]
+    fn matches_is_permissive_with_no_filters() {
+        let events = decode_all(RAW_LOG);
+        let build = events
+            .iter()
+            .find(|event| matches!(event.kind(), EventKind::Build { .. }))
+            .unwrap();
+
+        assert!(matches(&[], build.kind()));
+    }
+
+    #[test]
+    fn matches_filters_are_additive() {
+        let events = decode_all(RAW_LOG);
+        let build = events
+            .iter()
+            .find(|event| matches!(event.kind(), EventKind::Build { .. }))
+            .unwrap();
+        let message = events
+            .iter()
+            .find(|event| matches!(event.kind(), EventKind::UI(_)))
+            .unwrap();
+
+        assert!(matches(&[Filter::Builds], build.kind()));
+        assert!(!matches(&[Filter::Messages], build.kind()));
+        assert!(matches(&[Filter::Builds, Filter::Messages], build.kind()));
+        assert!(matches(&[Filter::Builds, Filter::Messages], message.kind()));
+    }
+}
@@ -0,0 +1,33 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("unexpected token '{actual}', expected '{expected}'")]
+    UnexpectedToken {
+        expected: &'static str,
+        actual: String,
+    },
+    #[error("missing field '{field}' in {structure}")]
+    MissingField {
+        structure: &'static str,
+        field: &'static str,
+    },
+    #[error("failed to parse integer field '{field}' in {structure}: {source}")]
+    ParseInt {
+        structure: &'static str,
+        field: &'static str,
+        source: std::num::ParseIntError,
+    },
+    #[error("unexpected message '{actual}' in {structure}, expected '{expected}'")]
+    UnexpectedMessage {
+        structure: &'static str,
+        expected: &'static str,
+        actual: String,
+    },
+    #[error("{structure} finished without all of its fields being filled in")]
+    IncompleteArtifact { structure: &'static str },
+    #[error("i/o error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("failed to watch log file: {0}")]
+    Watch(#[from] notify::Error),
+}
@@ -1,17 +1,32 @@
-#[derive(Debug, Clone)]
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
 pub enum UI {
     Say(String),
     Message(String),
     Error(String),
+    /// Sets the active target (build name) for subsequent UI output.
+    /// Packer emits this as its own `ui,target,<name>` message.
+    Target(String),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Event {
     pub(crate) timestamp: String,
     pub(crate) kind: EventKind,
 }
 
-#[derive(Debug, Clone)]
+impl Event {
+    pub fn timestamp(&self) -> &str {
+        &self.timestamp
+    }
+
+    pub fn kind(&self) -> &EventKind {
+        &self.kind
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub enum EventKind {
     UI(UI),
     Artifact {
@@ -21,16 +36,41 @@ pub enum EventKind {
     Build {
         build: Build,
     },
+    /// A non-artifact message scoped to a particular build, e.g. a `ui`
+    /// message tagged with that build's name, as opposed to the global
+    /// `UI` variant.
+    BuildMessage {
+        build_name: String,
+        message: UI,
+    },
+    /// The packer version preamble emitted at the start of every
+    /// machine-readable log.
+    Version {
+        version: String,
+    },
+    VersionCommit {
+        commit: String,
+    },
+    VersionPrerelease {
+        prerelease: String,
+    },
+    /// A message whose `message_type` this crate doesn't know how to
+    /// interpret yet. Carries the raw fields so logs round-trip without
+    /// losing data instead of failing to decode.
+    Unknown {
+        message_type: String,
+        fields: Vec<String>,
+    },
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Artifact {
     pub builder_id: String,
     pub id: Option<String>,
     pub files: Vec<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Build {
     pub artifacts: Vec<Artifact>,
 }
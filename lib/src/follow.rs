@@ -0,0 +1,220 @@
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+    sync::mpsc::channel,
+};
+
+use notify::{RecursiveMode, Watcher};
+
+use crate::{
+    error::Error,
+    event::Event,
+    log::{Decodeable, EventLog},
+};
+
+/// Watches `path` for appended lines, decoding them through an [`EventLog`]
+/// and invoking `callback` for every [`Event`] as it is produced, much like
+/// `tail -f`.
+///
+/// Handles log rotation/truncation (the file shrinking below the last
+/// consumed offset resets the read position to the start) as well as the
+/// watched path being briefly removed and recreated, by re-opening it once
+/// it reappears.
+///
+/// This call blocks until the watcher is dropped or errors out, so callers
+/// that want to stop following should run it on a dedicated thread.
+pub fn follow<P, F>(path: P, mut callback: F) -> Result<(), Error>
+where
+    P: AsRef<Path>,
+    F: FnMut(Event),
+{
+    let path = path.as_ref();
+    let (tx, rx) = channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+
+    // Watch the parent directory rather than the file itself: a watch on
+    // the file's inode doesn't survive the file being removed, so it would
+    // never see the `Create` for the replacement file a remove-and-recreate
+    // rotation produces. Watching the directory keeps the subscription
+    // alive across that gap.
+    let directory = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    watcher.watch(directory, RecursiveMode::NonRecursive)?;
+
+    let mut cursor = Cursor::default();
+
+    // Pick up anything already in the file before the first filesystem
+    // event arrives.
+    cursor.consume(path, &mut callback)?;
+
+    for event in rx {
+        match event {
+            // Compare by file name rather than the full path: the watched
+            // directory may be reported back with a different (e.g.
+            // relative-with-"./") representation than the one `path` was
+            // given in, even though it refers to the same file.
+            Ok(notify::Event { paths, kind, .. })
+                if paths.iter().any(|p| p.file_name() == path.file_name()) =>
+            {
+                match kind {
+                    notify::EventKind::Remove(_) => {
+                        // The file disappeared (e.g. rotated out); start
+                        // over once it's recreated.
+                        cursor.reset();
+                    }
+                    _ => cursor.consume(path, &mut callback)?,
+                }
+            }
+            // Unrelated to our file (another entry in the directory changed),
+            // or the watcher itself hiccuped; the next event will retry.
+            Ok(_) | Err(_) => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Tracks how much of the watched file has already been consumed.
+#[derive(Default)]
+struct Cursor {
+    offset: u64,
+    partial_line: String,
+    log: EventLog,
+}
+
+impl Cursor {
+    fn reset(&mut self) {
+        self.offset = 0;
+        self.partial_line.clear();
+        // The decoder's build state is only valid for the bytes we've
+        // already fed it; starting over from byte 0 without resetting it
+        // too would replay lines into builds that are already `Done`.
+        self.log = EventLog::default();
+    }
+
+    /// Reads whatever bytes have been appended since `offset`, splits off
+    /// complete lines and feeds them to the decoder, buffering any trailing
+    /// partial line until its newline arrives.
+    fn consume<F: FnMut(Event)>(&mut self, path: &Path, callback: &mut F) -> Result<(), Error> {
+        let mut file = match File::open(path) {
+            Ok(file) => file,
+            // Removed but not yet recreated; nothing to read until it is.
+            Err(_) => return Ok(()),
+        };
+
+        let len = file.metadata()?.len();
+        if len < self.offset {
+            // Rotated or truncated out from under us: start over.
+            self.reset();
+        }
+
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf)?;
+        self.offset += buf.len() as u64;
+        self.partial_line.push_str(&String::from_utf8_lossy(&buf));
+
+        while let Some(newline) = self.partial_line.find('\n') {
+            let line: String = self.partial_line.drain(..=newline).collect();
+            let line = line.trim_end_matches(['\n', '\r']);
+            if !line.is_empty() {
+                // A single unparseable line shouldn't bring down a
+                // long-running follow; skip it and keep going, same as the
+                // file/stdin loops in the CLI.
+                if let Err(err) = self.log.try_decode(line.split(","), &mut *callback) {
+                    eprintln!("skipping unparseable line: {}", err);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Seek, Write};
+
+    use super::Cursor;
+
+    fn events(log_file: &tempfile::NamedTempFile) -> Vec<String> {
+        let mut cursor = Cursor::default();
+        let mut events = Vec::new();
+        cursor
+            .consume(log_file.path(), &mut |event| {
+                events.push(format!("{:?}", event))
+            })
+            .unwrap();
+        events
+    }
+
+    #[test]
+    fn consumes_appended_lines_incrementally() {
+        let mut log_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(log_file, "1643024800,,version,2").unwrap();
+
+        let mut cursor = Cursor::default();
+        let mut first = Vec::new();
+        cursor
+            .consume(log_file.path(), &mut |event| {
+                first.push(format!("{:?}", event))
+            })
+            .unwrap();
+        assert_eq!(first.len(), 1);
+
+        writeln!(log_file, "1643024800,,version-commit,abcdef1234").unwrap();
+        let mut second = Vec::new();
+        cursor
+            .consume(log_file.path(), &mut |event| {
+                second.push(format!("{:?}", event))
+            })
+            .unwrap();
+        assert_eq!(second.len(), 1);
+        assert!(second[0].contains("VersionCommit"));
+    }
+
+    #[test]
+    fn truncation_resets_the_offset_and_decoder() {
+        let mut log_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(log_file, "1643024800,,version,2").unwrap();
+        writeln!(log_file, "1643024800,,version-commit,abcdef1234").unwrap();
+
+        let mut cursor = Cursor::default();
+        cursor.consume(log_file.path(), &mut |_| {}).unwrap();
+        assert!(cursor.offset > 0);
+
+        // Truncate back to a single, different line, as a log rotation
+        // that reuses the same inode would.
+        log_file.as_file().set_len(0).unwrap();
+        log_file.as_file_mut().rewind().unwrap();
+        writeln!(log_file, "1643024801,,version,3").unwrap();
+
+        let mut events = Vec::new();
+        cursor
+            .consume(log_file.path(), &mut |event| {
+                events.push(format!("{:?}", event))
+            })
+            .unwrap();
+
+        assert_eq!(events.len(), 1);
+        assert!(events[0].contains("\"3\""));
+    }
+
+    #[test]
+    fn malformed_line_does_not_wedge_subsequent_lines() {
+        let mut log_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(log_file, "1643024800,,version,2").unwrap();
+        // A bad field-count mid-artifact, followed by a well-formed
+        // continuation of the same protocol.
+        writeln!(log_file, "1643024801,some-build,artifact-count,NOTANUMBER").unwrap();
+        writeln!(log_file, "1643024802,,version-commit,abcdef1234").unwrap();
+
+        let found = events(&log_file);
+
+        assert!(found.iter().any(|event| event.contains("Version {")));
+        assert!(found.iter().any(|event| event.contains("VersionCommit")));
+    }
+}
@@ -0,0 +1,5 @@
+pub mod error;
+pub mod event;
+pub mod follow;
+pub mod log;
+pub mod stream;
@@ -0,0 +1,708 @@
+use std::{collections::HashMap, convert::TryFrom, str::Split};
+
+use crate::{
+    error::Error,
+    event::{Artifact, Build, Event, EventKind, UI},
+};
+
+impl TryFrom<PartialArtifactLog> for Artifact {
+    type Error = Error;
+
+    fn try_from(partial: PartialArtifactLog) -> Result<Self, Self::Error> {
+        if let PartialArtifactLog::Done(artifact) = partial {
+            Ok(artifact)
+        } else {
+            Err(Error::IncompleteArtifact {
+                structure: "partial artifact",
+            })
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+enum PartialArtifactLog {
+    #[default]
+    Root,
+    BuilderId {
+        builder_id: String,
+    },
+    Id {
+        builder_id: String,
+        id: Option<String>,
+    },
+    String {
+        builder_id: String,
+        id: Option<String>,
+        string: String,
+    },
+    ListingFiles {
+        builder_id: String,
+        id: Option<String>,
+        string: String,
+        count: usize,
+        files: Vec<Option<String>>,
+    },
+    Done(Artifact),
+}
+
+fn expect_message(
+    structure: &'static str,
+    expected: &'static str,
+    actual: &str,
+) -> Result<(), Error> {
+    if actual != expected {
+        Err(Error::UnexpectedMessage {
+            structure,
+            expected,
+            actual: actual.to_string(),
+        })
+    } else {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decoding {
+    Partial,
+    Done,
+}
+
+pub trait Decodeable {
+    type Error;
+    type Unit;
+    fn try_decode<F: FnMut(Self::Unit)>(
+        &mut self,
+        input: Split<&str>,
+        callback: F,
+    ) -> Result<Decoding, Self::Error>;
+}
+
+impl Decodeable for PartialArtifactLog {
+    type Error = Error;
+    type Unit = Artifact;
+
+    fn try_decode<F: FnMut(Self::Unit)>(
+        &mut self,
+        mut input: Split<&str>,
+        mut callback: F,
+    ) -> Result<Decoding, Self::Error> {
+        let message = input.next().ok_or(Error::MissingField {
+            structure: "partial artifact",
+            field: "message",
+        })?;
+
+        // Temporarily replace the contents of "self" with an empty PartialArtifactLog::Root,
+        // take the old content of self and mutate it, then replace the *self reference with
+        // the mutated version. This is basically a funky way of doing a mutate-in-place for
+        // a mutable reference to an enum.
+        //
+        // The whole thing is wrapped in a closure so that a `?` failing partway through a
+        // branch doesn't leave `self` stuck at `Root`: we only commit `next` to `*self` once
+        // we know decoding succeeded, otherwise the pre-replace state is restored below.
+        let previous = self.clone();
+        let next = (|| -> Result<PartialArtifactLog, Error> {
+            Ok(match std::mem::replace(self, PartialArtifactLog::Root) {
+                PartialArtifactLog::Root => {
+                    expect_message("partial artifact", "builder-id", message)?;
+
+                    let id = input.next().ok_or(Error::MissingField {
+                        structure: "partial artifact",
+                        field: "builder-id",
+                    })?;
+                    PartialArtifactLog::BuilderId {
+                        builder_id: id.to_string(),
+                    }
+                }
+                PartialArtifactLog::BuilderId { builder_id } => {
+                    expect_message("partial artifact", "id", message)?;
+
+                    let id = match input.next().ok_or(Error::MissingField {
+                        structure: "partial artifact",
+                        field: "id",
+                    })? {
+                        "" => None,
+                        s => Some(s.to_string()),
+                    };
+
+                    PartialArtifactLog::Id { builder_id, id }
+                }
+                PartialArtifactLog::Id { builder_id, id } => {
+                    expect_message("partial artifact", "string", message)?;
+
+                    let string = input
+                        .next()
+                        .ok_or(Error::MissingField {
+                            structure: "partial artifact",
+                            field: "string",
+                        })?
+                        .to_string();
+                    PartialArtifactLog::String {
+                        builder_id,
+                        id,
+                        string,
+                    }
+                }
+                PartialArtifactLog::String {
+                    builder_id,
+                    id,
+                    string,
+                } => {
+                    expect_message("partial artifact", "files-count", message)?;
+                    let count: usize = input
+                        .next()
+                        .ok_or(Error::MissingField {
+                            structure: "partial artifact",
+                            field: "files-count",
+                        })?
+                        .parse()
+                        .map_err(|source| Error::ParseInt {
+                            structure: "partial artifact",
+                            field: "files-count",
+                            source,
+                        })?;
+
+                    PartialArtifactLog::ListingFiles {
+                        builder_id,
+                        id,
+                        string,
+                        count,
+                        files: vec![None; count],
+                    }
+                }
+                PartialArtifactLog::ListingFiles {
+                    count,
+                    builder_id,
+                    id,
+                    string,
+                    mut files,
+                } => {
+                    if count == 0 {
+                        expect_message("partial artifact", "end", message)?;
+
+                        let files = files
+                            .into_iter()
+                            .map(|file| {
+                                file.ok_or(Error::IncompleteArtifact {
+                                    structure: "partial artifact",
+                                })
+                            })
+                            .collect::<Result<Vec<_>, Error>>()?;
+
+                        let artifact = Artifact {
+                            builder_id,
+                            id,
+                            files,
+                        };
+
+                        callback(artifact.clone());
+                        PartialArtifactLog::Done(artifact)
+                    } else {
+                        expect_message("partial artifact", "file", message)?;
+
+                        let file_id: usize = input
+                            .next()
+                            .ok_or(Error::MissingField {
+                                structure: "partial artifact",
+                                field: "file-id",
+                            })?
+                            .parse()
+                            .map_err(|source| Error::ParseInt {
+                                structure: "partial artifact",
+                                field: "file-id",
+                                source,
+                            })?;
+                        let file_name = input.next().ok_or(Error::MissingField {
+                            structure: "partial artifact",
+                            field: "file-name",
+                        })?;
+
+                        let slot = files.get_mut(file_id).ok_or(Error::MissingField {
+                            structure: "partial artifact",
+                            field: "file-id",
+                        })?;
+                        slot.replace(file_name.to_string());
+
+                        PartialArtifactLog::ListingFiles {
+                            builder_id,
+                            id,
+                            string,
+                            count: count - 1,
+                            files,
+                        }
+                    }
+                }
+                PartialArtifactLog::Done(_) => {
+                    return Err(Error::UnexpectedMessage {
+                        structure: "partial artifact",
+                        expected: "<no further messages>",
+                        actual: message.to_string(),
+                    })
+                }
+            })
+        })();
+
+        *self = match next {
+            Ok(state) => state,
+            Err(error) => {
+                *self = previous;
+                return Err(error);
+            }
+        };
+
+        Ok(if let PartialArtifactLog::Done(_) = self {
+            Decoding::Done
+        } else {
+            Decoding::Partial
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+enum PartialBuildLog {
+    Root,
+    ListingArtifacts {
+        count: usize,
+        artifacts: Vec<Option<PartialArtifactLog>>,
+    },
+    // The completed build is only ever matched against as a state marker
+    // here; the actual value is handed to the caller via `callback` before
+    // this variant is reached.
+    Done(#[allow(dead_code)] Build),
+}
+
+enum BuildLogEventKind {
+    Artifact(Artifact),
+    Done(Build),
+}
+
+impl Decodeable for PartialBuildLog {
+    type Error = Error;
+    type Unit = BuildLogEventKind;
+
+    fn try_decode<F: FnMut(Self::Unit)>(
+        &mut self,
+        mut input: Split<&str>,
+        mut callback: F,
+    ) -> Result<Decoding, Self::Error> {
+        let message = input.next().ok_or(Error::MissingField {
+            structure: "partial build",
+            field: "message",
+        })?;
+
+        // See the comment in `PartialArtifactLog::try_decode` above: committing `next` only on
+        // success keeps a failed line from wedging this build at `Root` forever.
+        let previous = self.clone();
+        let next = (|| -> Result<PartialBuildLog, Error> {
+            Ok(match std::mem::replace(self, PartialBuildLog::Root) {
+                PartialBuildLog::Root => {
+                    expect_message("partial build", "artifact-count", message)?;
+                    let count: usize = input
+                        .next()
+                        .ok_or(Error::MissingField {
+                            structure: "partial build",
+                            field: "artifact-count",
+                        })?
+                        .parse()
+                        .map_err(|source| Error::ParseInt {
+                            structure: "partial build",
+                            field: "artifact-count",
+                            source,
+                        })?;
+
+                    PartialBuildLog::ListingArtifacts {
+                        count,
+                        artifacts: vec![None; count],
+                    }
+                }
+                PartialBuildLog::ListingArtifacts {
+                    mut count,
+                    mut artifacts,
+                } => {
+                    expect_message("partial build", "artifact", message)?;
+
+                    let artifact_id: usize = input
+                        .next()
+                        .ok_or(Error::MissingField {
+                            structure: "partial build",
+                            field: "artifact-id",
+                        })?
+                        .parse()
+                        .map_err(|source| Error::ParseInt {
+                            structure: "partial build",
+                            field: "artifact-id",
+                            source,
+                        })?;
+
+                    let slot = artifacts.get_mut(artifact_id).ok_or(Error::MissingField {
+                        structure: "partial build",
+                        field: "artifact-id",
+                    })?;
+
+                    let decoded_artifact = slot
+                        .get_or_insert(PartialArtifactLog::Root)
+                        .try_decode(input, |artifact| {
+                            callback(BuildLogEventKind::Artifact(artifact))
+                        })?;
+
+                    // Only ever decrement the counter when we're 100% finished decoding
+                    // an artifact. This way we can keep track of whether a Build is done,
+                    // by checking if any un-decoded artifacts lay ahead.
+                    if decoded_artifact == Decoding::Done {
+                        count -= 1;
+                    };
+
+                    if count == 0 {
+                        let artifacts = artifacts
+                            .into_iter()
+                            .map(|artifact| {
+                                artifact
+                                    .ok_or(Error::IncompleteArtifact {
+                                        structure: "partial build",
+                                    })
+                                    .and_then(Artifact::try_from)
+                            })
+                            .collect::<Result<Vec<_>, Error>>()?;
+
+                        let build = Build { artifacts };
+
+                        callback(BuildLogEventKind::Done(build.clone()));
+
+                        PartialBuildLog::Done(build)
+                    } else {
+                        PartialBuildLog::ListingArtifacts { count, artifacts }
+                    }
+                }
+                PartialBuildLog::Done(_) => {
+                    return Err(Error::UnexpectedMessage {
+                        structure: "partial build",
+                        expected: "<no further messages>",
+                        actual: message.to_string(),
+                    })
+                }
+            })
+        })();
+
+        *self = match next {
+            Ok(state) => state,
+            Err(error) => {
+                *self = previous;
+                return Err(error);
+            }
+        };
+
+        Ok(if let PartialBuildLog::Done(_) = self {
+            Decoding::Done
+        } else {
+            Decoding::Partial
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct EventLog {
+    builds: HashMap<String, PartialBuildLog>,
+}
+
+impl Decodeable for EventLog {
+    type Error = Error;
+    type Unit = Event;
+    fn try_decode<F: FnMut(Self::Unit)>(
+        &mut self,
+        mut input: Split<&str>,
+        mut callback: F,
+    ) -> Result<Decoding, Self::Error> {
+        let timestamp = input
+            .next()
+            .ok_or(Error::MissingField {
+                structure: "event",
+                field: "timestamp",
+            })?
+            .to_string();
+        let build_name = input.next().ok_or(Error::MissingField {
+            structure: "event",
+            field: "build_name",
+        })?;
+
+        // If this isn't tied to build_name, then it's global.
+        if build_name.is_empty() {
+            let message_type = input.next().ok_or(Error::MissingField {
+                structure: "event",
+                field: "message_type",
+            })?;
+
+            let kind = match message_type {
+                "ui" => decode_ui(input)?,
+                "version" => EventKind::Version {
+                    version: input
+                        .next()
+                        .ok_or(Error::MissingField {
+                            structure: "version",
+                            field: "version",
+                        })?
+                        .to_string(),
+                },
+                "version-commit" => EventKind::VersionCommit {
+                    commit: input
+                        .next()
+                        .ok_or(Error::MissingField {
+                            structure: "version-commit",
+                            field: "commit",
+                        })?
+                        .to_string(),
+                },
+                "version-prerelease" => EventKind::VersionPrerelease {
+                    prerelease: input
+                        .next()
+                        .ok_or(Error::MissingField {
+                            structure: "version-prerelease",
+                            field: "prerelease",
+                        })?
+                        .to_string(),
+                },
+                _ => EventKind::Unknown {
+                    message_type: message_type.to_string(),
+                    fields: input.map(str::to_string).collect(),
+                },
+            };
+
+            callback(Event { timestamp, kind });
+        } else {
+            // Peek at the message type without consuming it: if it's a
+            // per-build `ui` message, decode it directly; if it belongs to
+            // the artifact sub-protocol, hand it to `PartialBuildLog`
+            // (which reads the same token as its own first message);
+            // anything else is a per-build lifecycle/plugin message we
+            // don't understand yet.
+            let mut peek = input.clone();
+            let message_type = peek.next().ok_or(Error::MissingField {
+                structure: "event",
+                field: "message_type",
+            })?;
+
+            match message_type {
+                "ui" => {
+                    input.next();
+
+                    let kind = match decode_ui(input)? {
+                        EventKind::UI(message) => EventKind::BuildMessage {
+                            build_name: build_name.to_string(),
+                            message,
+                        },
+                        // An unrecognized `ui` subtype: prepend build_name
+                        // the same way the catch-all arm below does, so it
+                        // isn't silently dropped from the round-tripped
+                        // fields.
+                        EventKind::Unknown {
+                            message_type,
+                            fields,
+                        } => EventKind::Unknown {
+                            message_type,
+                            fields: std::iter::once(build_name.to_string())
+                                .chain(fields)
+                                .collect(),
+                        },
+                        other => other,
+                    };
+
+                    callback(Event { timestamp, kind });
+                }
+                "artifact-count" | "artifact" => {
+                    let enrich = |build_event| {
+                        callback(Event {
+                            // We have to clone the timestamp here to make this function Fn and not FnOnce,
+                            // because the function could be called multiple times from the following
+                            // Decodeable::try_decode call.
+                            timestamp: timestamp.clone(),
+                            kind: match build_event {
+                                BuildLogEventKind::Artifact(artifact) => EventKind::Artifact {
+                                    build_name: build_name.to_string(),
+                                    artifact,
+                                },
+                                BuildLogEventKind::Done(build) => EventKind::Build { build },
+                            },
+                        })
+                    };
+
+                    let log = self
+                        .builds
+                        .entry(build_name.to_string())
+                        .or_insert(PartialBuildLog::Root);
+
+                    log.try_decode(input, enrich)?;
+                }
+                _ => callback(Event {
+                    timestamp,
+                    kind: EventKind::Unknown {
+                        message_type: message_type.to_string(),
+                        fields: std::iter::once(build_name.to_string())
+                            .chain(input.map(str::to_string))
+                            .collect(),
+                    },
+                }),
+            }
+        }
+
+        Ok(Decoding::Partial)
+    }
+}
+
+/// Decodes a global or per-build `ui` message (`say`/`message`/`error`/
+/// `target`), routing anything else into [`EventKind::Unknown`].
+fn decode_ui(mut input: Split<&str>) -> Result<EventKind, Error> {
+    let ui_type = input.next().ok_or(Error::MissingField {
+        structure: "ui event",
+        field: "type",
+    })?;
+
+    Ok(match ui_type {
+        "say" | "message" | "error" | "target" => {
+            let message = input
+                .next()
+                .ok_or(Error::MissingField {
+                    structure: "ui event",
+                    field: "message",
+                })?
+                .to_string();
+
+            EventKind::UI(match ui_type {
+                "say" => UI::Say(message),
+                "message" => UI::Message(message),
+                "error" => UI::Error(message),
+                "target" => UI::Target(message),
+                _ => unreachable!(),
+            })
+        }
+        _ => EventKind::Unknown {
+            message_type: "ui".to_string(),
+            fields: std::iter::once(ui_type.to_string())
+                .chain(input.map(str::to_string))
+                .collect(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EventLog;
+    use crate::{
+        event::{EventKind, UI},
+        log::Decodeable,
+    };
+
+    #[test]
+    pub fn parse_build_log() {
+        let raw_log = include_str!("../tests/example-build.log");
+
+        let mut log = EventLog::default();
+        let mut events = Vec::new();
+        for line in raw_log.lines() {
+            log.try_decode(line.split(","), |event| events.push(event))
+                .unwrap();
+        }
+
+        // The artifact sub-protocol lines (artifact-count, and all but the
+        // last `artifact` field) only update internal decoder state; only
+        // the version preamble, the ui messages, the unrecognized per-build
+        // message and the final artifact/build completion actually emit.
+        assert_eq!(events.len(), 9);
+
+        assert!(matches!(events[0].kind(), EventKind::Version { version } if version == "2"));
+        assert!(
+            matches!(events[1].kind(), EventKind::VersionCommit { commit } if commit == "abcdef1234")
+        );
+        assert!(
+            matches!(events[2].kind(), EventKind::VersionPrerelease { prerelease } if prerelease.is_empty())
+        );
+        assert!(
+            matches!(events[3].kind(), EventKind::UI(UI::Say(message)) if message == "Packer v1.7.0")
+        );
+
+        match events[4].kind() {
+            EventKind::BuildMessage {
+                build_name,
+                message,
+            } => {
+                assert_eq!(build_name, "build-name.virtualbox-iso");
+                assert!(
+                    matches!(message, UI::Target(target) if target == "build-name.virtualbox-iso")
+                );
+            }
+            other => panic!("expected BuildMessage, got {:?}", other),
+        }
+
+        match events[6].kind() {
+            EventKind::Unknown {
+                message_type,
+                fields,
+            } => {
+                assert_eq!(message_type, "provision-progress");
+                assert_eq!(fields[0], "build-name.virtualbox-iso");
+                assert_eq!(fields[2], "installer");
+            }
+            other => panic!("expected Unknown, got {:?}", other),
+        }
+
+        match events[7].kind() {
+            EventKind::Artifact {
+                build_name,
+                artifact,
+            } => {
+                assert_eq!(build_name, "build-name.virtualbox-iso");
+                assert_eq!(artifact.builder_id, "mitchellh.virtualbox");
+                assert_eq!(artifact.files, ["output-build-name/build-name.ovf"]);
+            }
+            other => panic!("expected Artifact, got {:?}", other),
+        }
+
+        match events[8].kind() {
+            EventKind::Build { build } => assert_eq!(build.artifacts.len(), 1),
+            other => panic!("expected Build, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn recovers_from_a_malformed_line() {
+        let mut log = EventLog::default();
+        let mut events = Vec::new();
+        let mut decode = |line: &str| log.try_decode(line.split(","), |event| events.push(event));
+
+        decode("1643024810,some-build,artifact-count,1").unwrap();
+        decode("1643024811,some-build,artifact,0,builder-id,mitchellh.virtualbox").unwrap();
+        decode("1643024811,some-build,artifact,0,id,").unwrap();
+        decode("1643024811,some-build,artifact,0,string,VM files").unwrap();
+
+        // A garbled files-count shouldn't wedge this build's decoder at
+        // `Root` forever: the next well-formed files-count for the same
+        // build must still be accepted.
+        decode("1643024811,some-build,artifact,0,files-count,NOTANUMBER").unwrap_err();
+        decode("1643024811,some-build,artifact,0,files-count,0").unwrap();
+        decode("1643024811,some-build,artifact,0,end").unwrap();
+
+        assert!(events
+            .iter()
+            .any(|event| matches!(event.kind(), EventKind::Build { .. })));
+    }
+
+    #[test]
+    fn unrecognized_per_build_ui_subtype_keeps_the_build_name() {
+        let mut log = EventLog::default();
+        let mut events = Vec::new();
+        log.try_decode(
+            "1643024801,some-build,ui,weird-subtype,payload".split(","),
+            |event| events.push(event),
+        )
+        .unwrap();
+
+        match events[0].kind() {
+            EventKind::Unknown {
+                message_type,
+                fields,
+            } => {
+                assert_eq!(message_type, "ui");
+                assert_eq!(fields[0], "some-build");
+                assert_eq!(fields[1], "weird-subtype");
+            }
+            other => panic!("expected Unknown, got {:?}", other),
+        }
+    }
+}
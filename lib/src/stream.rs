@@ -0,0 +1,119 @@
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{
+    io::{AsyncBufRead, AsyncBufReadExt, Lines},
+    Stream,
+};
+
+use crate::{
+    error::Error,
+    event::Event,
+    log::{Decodeable, EventLog},
+};
+
+/// Adapts an [`EventLog`] into an async [`Stream`] of [`Event`]s, so
+/// consumers can drive the parser from any `AsyncBufRead` (a spawned
+/// `packer build -machine-readable` child's stdout, a socket, etc.) and
+/// receive events as they're decoded, instead of only through a
+/// synchronous line loop.
+pub fn event_stream<R: AsyncBufRead + Unpin>(reader: R) -> EventStream<R> {
+    EventStream {
+        lines: reader.lines(),
+        log: EventLog::default(),
+        pending: VecDeque::new(),
+    }
+}
+
+/// The [`Stream`] returned by [`event_stream`].
+pub struct EventStream<R> {
+    lines: Lines<R>,
+    log: EventLog,
+    pending: VecDeque<Event>,
+}
+
+impl<R: AsyncBufRead + Unpin> Stream for EventStream<R> {
+    type Item = Result<Event, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if let Some(event) = this.pending.pop_front() {
+                return Poll::Ready(Some(Ok(event)));
+            }
+
+            match Pin::new(&mut this.lines).poll_next(cx) {
+                Poll::Ready(Some(Ok(line))) => {
+                    let pending = &mut this.pending;
+                    if let Err(err) = this
+                        .log
+                        .try_decode(line.split(","), |event| pending.push_back(event))
+                    {
+                        // Surface the bad line as an error item, but keep
+                        // polling on subsequent calls rather than ending
+                        // the stream, so one malformed line doesn't take
+                        // down a long-running consumer.
+                        return Poll::Ready(Some(Err(err)));
+                    }
+                }
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(Error::Io(err)))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, io::Cursor, StreamExt};
+
+    use super::event_stream;
+    use crate::event::EventKind;
+
+    #[test]
+    fn decodes_multiple_events_from_a_single_line_in_order() {
+        // The final `end` line of an artifact completes both the artifact
+        // and (being the only one) the build, so it alone yields two events.
+        let raw = concat!(
+            "1643024810,some-build,artifact-count,1\n",
+            "1643024811,some-build,artifact,0,builder-id,mitchellh.virtualbox\n",
+            "1643024811,some-build,artifact,0,id,\n",
+            "1643024811,some-build,artifact,0,string,VM files\n",
+            "1643024811,some-build,artifact,0,files-count,0\n",
+            "1643024811,some-build,artifact,0,end\n",
+        );
+
+        let events: Vec<_> = block_on(event_stream(Cursor::new(raw.as_bytes())).collect());
+
+        assert_eq!(events.len(), 2);
+        assert!(matches!(
+            events[0].as_ref().unwrap().kind(),
+            EventKind::Artifact { .. }
+        ));
+        assert!(matches!(
+            events[1].as_ref().unwrap().kind(),
+            EventKind::Build { .. }
+        ));
+    }
+
+    #[test]
+    fn malformed_line_surfaces_as_an_error_item_without_ending_the_stream() {
+        let raw = concat!(
+            "1643024800,,version,2\n",
+            "1643024801,some-build,artifact-count,NOTANUMBER\n",
+            "1643024802,,version-commit,abcdef1234\n",
+        );
+
+        let events: Vec<_> = block_on(event_stream(Cursor::new(raw.as_bytes())).collect());
+
+        assert_eq!(events.len(), 3);
+        assert!(events[0].is_ok());
+        assert!(events[1].is_err());
+        assert!(events[2].is_ok());
+    }
+}